@@ -0,0 +1,153 @@
+//! OAM DMA engine driven by FF46.
+//!
+//! A write to FF46 latches a source page (`value << 8`) and copies 160 bytes
+//! into OAM over 160 machine cycles (640 dots), after a short start-up delay.
+//! The transfer state is kept here, separate from the PPU mode machine: the
+//! bus reads the source range and hands the bytes back via `fill`, while the
+//! PPU forwards its cycle count to `tick` to pace the copy into OAM.
+
+const OAM_DMA_LEN: usize = 0xA0;
+const DOTS_PER_BYTE: usize = 4;
+const STARTUP_DOTS: usize = 4;
+
+pub struct OamDma {
+    pub active: bool,
+    pending: bool,
+    source: u16,
+    buffer: [u8; OAM_DMA_LEN],
+    index: usize,
+    clock: usize,
+    started: bool,
+}
+
+impl OamDma {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            pending: false,
+            source: 0,
+            buffer: [0; OAM_DMA_LEN],
+            index: 0,
+            clock: 0,
+            started: false,
+        }
+    }
+
+    // A write to FF46 latches the source page and (re)starts the transfer.
+    pub fn start(&mut self, value: u8) {
+        self.source = (value as u16) << 8;
+        self.active = true;
+        self.pending = true;
+        self.index = 0;
+        self.clock = 0;
+        self.started = false;
+    }
+
+    // Source range the bus must read and hand back via `fill` before any byte
+    // moves, expressed as the inclusive base `source ..= source | 0x9F`.
+    pub fn request(&self) -> Option<u16> {
+        if self.pending {
+            Some(self.source)
+        } else {
+            None
+        }
+    }
+
+    // Stage the source bytes the bus just read.
+    pub fn fill(&mut self, bytes: &[u8]) {
+        let n = bytes.len().min(OAM_DMA_LEN);
+        self.buffer[..n].copy_from_slice(&bytes[..n]);
+        self.pending = false;
+    }
+
+    // Advance the transfer, moving one staged byte into OAM per machine cycle
+    // after the start-up delay and clearing `active` once the final byte lands.
+    pub fn tick(&mut self, cycles: usize, oam: &mut [u8]) {
+        if !self.active || self.pending {
+            return;
+        }
+        self.clock += cycles;
+
+        if !self.started {
+            if self.clock < STARTUP_DOTS {
+                return;
+            }
+            self.clock -= STARTUP_DOTS;
+            self.started = true;
+        }
+
+        while self.clock >= DOTS_PER_BYTE && self.index < OAM_DMA_LEN {
+            oam[self.index] = self.buffer[self.index];
+            self.index += 1;
+            self.clock -= DOTS_PER_BYTE;
+        }
+
+        if self.index >= OAM_DMA_LEN {
+            self.active = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn staged() -> ([u8; OAM_DMA_LEN], OamDma) {
+        let src: [u8; OAM_DMA_LEN] = core::array::from_fn(|i| i as u8);
+        let mut dma = OamDma::new();
+        dma.start(0xC0);
+        assert_eq!(dma.request(), Some(0xC000));
+        dma.fill(&src);
+        assert_eq!(dma.request(), None);
+        (src, dma)
+    }
+
+    #[test]
+    fn pending_until_filled() {
+        let mut oam = [0u8; OAM_DMA_LEN];
+        let mut dma = OamDma::new();
+        dma.start(0x80);
+        // No source bytes yet: ticking must not move anything and must stay active.
+        dma.tick(1000, &mut oam);
+        assert!(dma.active);
+        assert_eq!(oam, [0u8; OAM_DMA_LEN]);
+    }
+
+    #[test]
+    fn startup_delay_precedes_first_byte() {
+        let (_src, mut dma) = staged();
+        let mut oam = [0xFFu8; OAM_DMA_LEN];
+        // The start-up delay is consumed before any byte moves.
+        dma.tick(STARTUP_DOTS, &mut oam);
+        assert_eq!(oam[0], 0xFF);
+        // One machine cycle later the first byte lands.
+        dma.tick(DOTS_PER_BYTE, &mut oam);
+        assert_eq!(oam[0], 0x00);
+        assert_eq!(oam[1], 0xFF);
+        assert!(dma.active);
+    }
+
+    #[test]
+    fn copies_all_bytes_then_clears_active() {
+        let (src, mut dma) = staged();
+        let mut oam = [0u8; OAM_DMA_LEN];
+        dma.tick(STARTUP_DOTS + OAM_DMA_LEN * DOTS_PER_BYTE, &mut oam);
+        assert_eq!(oam, src);
+        assert!(!dma.active);
+    }
+
+    #[test]
+    fn pacing_is_one_byte_per_machine_cycle() {
+        let (src, mut dma) = staged();
+        let mut oam = [0u8; OAM_DMA_LEN];
+        dma.tick(STARTUP_DOTS, &mut oam);
+        for i in 0..OAM_DMA_LEN {
+            dma.tick(DOTS_PER_BYTE, &mut oam);
+            assert_eq!(oam[i], src[i]);
+            if i + 1 < OAM_DMA_LEN {
+                assert_eq!(oam[i + 1], 0);
+            }
+        }
+        assert!(!dma.active);
+    }
+}
@@ -1,7 +1,9 @@
+pub mod dma;
 pub mod registers;
 pub mod tiles;
 
 use crate::cpu::EmulationMode;
+use crate::gpu::dma::OamDma;
 use crate::gpu::registers::{ColorPalette, LcdControl, LcdPosition, LcdStatus, MonochromePalette};
 use crate::gpu::tiles::{BgAttr, Sprite};
 use std::collections::VecDeque;
@@ -40,11 +42,27 @@ impl From<&GpuMode> for u8 {
     }
 }
 
-#[derive(PartialEq)]
-enum PixelType {
-    BgColor0,
-    BgColorOpaque,
-    BgPriorityOverride,
+// A named four-shade DMG color scheme. The shades are ordered from color 0
+// (lightest in the stock green scheme) to color 3.
+#[derive(Clone, Copy)]
+pub enum DmgPalette {
+    OriginalGreen,
+    Grayscale,
+    HighContrast,
+}
+
+impl DmgPalette {
+    pub fn shades(&self) -> [(u8, u8, u8); 4] {
+        match self {
+            DmgPalette::OriginalGreen => {
+                [(224, 247, 208), (136, 192, 112), (52, 104, 86), (8, 23, 33)]
+            }
+            DmgPalette::Grayscale => [(255, 255, 255), (170, 170, 170), (85, 85, 85), (0, 0, 0)],
+            DmgPalette::HighContrast => {
+                [(255, 255, 255), (200, 200, 200), (60, 60, 60), (0, 0, 0)]
+            }
+        }
+    }
 }
 
 pub enum FetcherState {
@@ -65,6 +83,7 @@ pub struct Fetcher {
     pub fetching: FetchType,
     pub x: u8,
     pub tile_num: u8,
+    pub bg_attr: BgAttr,
     pub low: u8,
     pub high: u8,
 }
@@ -76,12 +95,36 @@ impl Fetcher {
             fetching: FetchType::Background,
             x: 0,
             tile_num: 0,
+            bg_attr: BgAttr::default(),
             low: 0xFF,
             high: 0xFF,
         }
     }
 }
 
+// A single object-layer pixel waiting to be merged over the background. The
+// color index is kept raw (0 means transparent) so the priority test can run
+// at pop time; `obp1` selects the DMG palette and `bg_priority` carries the
+// sprite's BG-over-OBJ attribute bit.
+#[derive(Clone, Copy)]
+pub struct ObjPixel {
+    pub color: u8,
+    pub obp1: bool,
+    pub cgb_palette: usize,
+    pub bg_priority: bool,
+}
+
+impl ObjPixel {
+    fn transparent() -> Self {
+        Self {
+            color: 0,
+            obp1: false,
+            cgb_palette: 0,
+            bg_priority: false,
+        }
+    }
+}
+
 pub struct BgFifo {
     pub q: VecDeque<u8>,
     pub x: u8,
@@ -109,11 +152,12 @@ impl BgFifo {
         self.q.len() <= 8
     }
 
-    pub fn push(&mut self, mut low: u8, mut high: u8) {
-        for _ in 0..8 {
-            self.q.push_back((low >> 7) | ((high >> 7) << 1));
-            low <<= 1;
-            high <<= 1;
+    // Push a tile row, one packed entry per pixel. `xflip` reverses the pixel
+    // order; the palette and priority ride along unchanged (see `pack_bg_pixel`).
+    pub fn push(&mut self, low: u8, high: u8, palette: u8, priority: bool, xflip: bool) {
+        for x in 0..8 {
+            let color = tile_color_at(low, high, x, xflip);
+            self.q.push_back(pack_bg_pixel(color, palette, priority));
         }
     }
 
@@ -122,6 +166,54 @@ impl BgFifo {
     }
 }
 
+// Number of leading FIFO pixels a window with WX < 7 shifts off the left edge;
+// a normally positioned window discards nothing.
+fn window_left_discard(window_x: u8) -> u8 {
+    7u8.saturating_sub(window_x)
+}
+
+// 2-bit color of pixel `x` (0 = leftmost) in a tile row's low/high byte pair.
+// `xflip` mirrors the row by reading the bits in reverse.
+fn tile_color_at(low: u8, high: u8, x: u8, xflip: bool) -> u8 {
+    let bit = if xflip { x } else { 7 - x };
+    ((low >> bit) & 1) | (((high >> bit) & 1) << 1)
+}
+
+// Row within a tile, accounting for CGB vertical flip.
+fn flip_tile_row(row: u8, yflip: bool) -> u8 {
+    if yflip {
+        7 - row
+    } else {
+        row
+    }
+}
+
+// A background FIFO entry packs the 2-bit color (bits 0-1), the CGB palette
+// number (bits 2-4) and the BG-to-OAM priority bit (bit 5) into one byte.
+fn pack_bg_pixel(color: u8, palette: u8, priority: bool) -> u8 {
+    (color & 0x03) | ((palette & 0x07) << 2) | ((priority as u8) << 5)
+}
+
+fn bg_pixel_color(entry: u8) -> u8 {
+    entry & 0x03
+}
+
+fn bg_pixel_palette(entry: u8) -> usize {
+    ((entry >> 2) & 0x07) as usize
+}
+
+fn bg_pixel_priority(entry: u8) -> bool {
+    (entry >> 5) & 0x01 != 0
+}
+
+// Priority resolution for one merged pixel: the object pixel is drawn unless it
+// is transparent (color 0) or the background is opaque and either the sprite's
+// own BG-over-OBJ bit or the CGB BG-to-OAM master priority bit keeps it behind.
+fn obj_wins(obj_color: u8, obj_bg_priority: bool, bg_color: u8, bg_master_priority: bool) -> bool {
+    let bg_over_obj = (obj_bg_priority || bg_master_priority) && bg_color != 0;
+    obj_color != 0 && !bg_over_obj
+}
+
 pub struct Gpu {
     pub lcd: Vec<u8>,
     pub vram0: Vec<u8>,
@@ -131,9 +223,9 @@ pub struct Gpu {
     cgbp: ColorPalette,
     emu_mode: EmulationMode,
     oam: Vec<u8>,
-    pixel_types: Vec<PixelType>,
     lcdc: LcdControl,
     dmgp: MonochromePalette,
+    dmg_palette: [(u8, u8, u8); 4],
     position: LcdPosition,
     stat: LcdStatus,
     clock: usize,
@@ -141,21 +233,22 @@ pub struct Gpu {
     pub request_lcd_int: bool,
     vram_bank: usize,
     win_counter: usize,
+    win_active: bool,
+    win_drawn: bool,
     pub oam_dma_active: bool,
+    oam_dma: OamDma,
+    tile_atlas_buf: Vec<u8>,
 
     // Pixel Pipeline
     bg_fifo: BgFifo,
+    obj_fifo: VecDeque<ObjPixel>,
+    line_sprites: Vec<Sprite>,
     fetcher: Fetcher,
     borrowed_cycles: usize,
 }
 
 impl Gpu {
     pub fn new(emu_mode: EmulationMode) -> Self {
-        let mut pixel_types = vec![];
-        for _ in 0..SCREEN_WIDTH {
-            pixel_types.push(PixelType::BgColor0);
-        }
-
         Gpu {
             lcd: vec![0; SCREEN_HEIGHT * SCREEN_WIDTH * SCREEN_DEPTH],
             vram0: vec![0; VRAM_BANK_SIZE],
@@ -165,9 +258,9 @@ impl Gpu {
             oam: vec![0; OAM_SIZE],
             cgbp: ColorPalette::default(),
             emu_mode,
-            pixel_types,
             lcdc: LcdControl::default(),
             dmgp: MonochromePalette::default(),
+            dmg_palette: DmgPalette::OriginalGreen.shades(),
             position: LcdPosition::default(),
             stat: LcdStatus::default(),
             clock: 0,
@@ -175,10 +268,16 @@ impl Gpu {
             request_lcd_int: false,
             vram_bank: 0,
             win_counter: 0,
+            win_active: false,
+            win_drawn: false,
             oam_dma_active: false,
+            oam_dma: OamDma::new(),
+            tile_atlas_buf: Vec::new(),
 
             // Pixel Pipeline
             bg_fifo: BgFifo::new(),
+            obj_fifo: VecDeque::with_capacity(8),
+            line_sprites: Vec::with_capacity(10),
             fetcher: Fetcher::new(),
             borrowed_cycles: 0,
         }
@@ -192,6 +291,135 @@ impl Gpu {
         self.lcd.as_ptr()
     }
 
+    // ----------------------------------------------------------------------
+    // VRAM debug surfaces
+    //
+    // These render the raw contents of VRAM independent of what the game is
+    // drawing, so a host can blit them into a separate inspector window. They
+    // reuse the normal tile-data addressing and palette decoders so the colors
+    // match the live frame for both DMG and CGB.
+    // ----------------------------------------------------------------------
+
+    // All tile patterns tiled into a 16-wide RGBA grid: 384 tiles from bank 0
+    // in DMG, or 768 tiles (bank 0 then bank 1) in CGB. Returns a pointer into
+    // an internally owned buffer, mirroring `screen`.
+    pub fn tile_atlas(&mut self) -> *const u8 {
+        let tiles = if self.emu_mode == EmulationMode::Cgb {
+            768
+        } else {
+            384
+        };
+        let cols = 16;
+        let width = cols * 8;
+        let height = (tiles / cols) * 8;
+        self.tile_atlas_buf.resize(width * height * SCREEN_DEPTH, 0);
+
+        for tile in 0..tiles {
+            let bank = tile / 384;
+            let addr = 0x8000 + (tile % 384) as u16 * 16;
+            let tx = (tile % cols) * 8;
+            let ty = (tile / cols) * 8;
+
+            for ry in 0..8 {
+                let low = self.get_vram_byte(addr + ry as u16 * 2, bank);
+                let high = self.get_vram_byte(addr + ry as u16 * 2 + 1, bank);
+                for rx in 0..8 {
+                    let bit = 7 - rx;
+                    let color = ((low >> bit) & 1) | (((high >> bit) & 1) << 1);
+                    let (r, g, b) = self.atlas_color(color);
+                    let idx = ((ty + ry) * width + tx + rx) * SCREEN_DEPTH;
+                    self.tile_atlas_buf[idx] = r;
+                    self.tile_atlas_buf[idx + 1] = g;
+                    self.tile_atlas_buf[idx + 2] = b;
+                    self.tile_atlas_buf[idx + 3] = 255;
+                }
+            }
+        }
+
+        self.tile_atlas_buf.as_ptr()
+    }
+
+    // One of the two 32x32 background tilemaps rendered as a full 256x256 RGBA
+    // image, with the current scroll and window viewports outlined. `which`
+    // selects the 0x9800 (0) or 0x9C00 (1) map.
+    pub fn tilemap_view(&self, which: u8) -> Vec<u8> {
+        const DIM: usize = 256;
+        let base = if which == 0 { 0x9800 } else { 0x9C00 };
+        let mut buf = vec![0u8; DIM * DIM * SCREEN_DEPTH];
+
+        for ty in 0..32u16 {
+            for tx in 0..32u16 {
+                let map_addr = base + ty * 32 + tx;
+                let tile_num = self.get_vram_byte(map_addr, 0);
+                let attr = match self.emu_mode {
+                    EmulationMode::Cgb => BgAttr::from(self.get_vram_byte(map_addr, 1)),
+                    _ => BgAttr::default(),
+                };
+                let tile_addr = self.tiledata_addr(self.lcdc.bg_tiledata_sel, tile_num);
+
+                for ry in 0..8u16 {
+                    let srow = if attr.yflip { 7 - ry } else { ry };
+                    let low = self.get_vram_byte(tile_addr + srow * 2, attr.bank);
+                    let high = self.get_vram_byte(tile_addr + srow * 2 + 1, attr.bank);
+                    for rx in 0..8u16 {
+                        let bit = if attr.xflip { rx } else { 7 - rx };
+                        let color = ((low >> bit) & 1) | (((high >> bit) & 1) << 1);
+                        let (r, g, b) = match self.emu_mode {
+                            EmulationMode::Cgb => {
+                                self.get_rgb_cgb(color, attr.palette_num as usize, false)
+                            }
+                            _ => self.get_rgb(color, self.dmgp.bgp),
+                        };
+                        let px = (tx * 8 + rx) as usize;
+                        let py = (ty * 8 + ry) as usize;
+                        let idx = (py * DIM + px) * SCREEN_DEPTH;
+                        buf[idx] = r;
+                        buf[idx + 1] = g;
+                        buf[idx + 2] = b;
+                        buf[idx + 3] = 255;
+                    }
+                }
+            }
+        }
+
+        // Scroll viewport (red) and window origin (green) outlines. A WX < 7
+        // window is shifted off the left edge, so its on-screen origin clamps to
+        // column 0 rather than wrapping around to the far right.
+        self.outline(&mut buf, self.position.scroll_x, self.position.scroll_y, (255, 0, 0));
+        let wx = self.position.window_x.checked_sub(7).unwrap_or(0);
+        self.outline(&mut buf, wx, self.position.window_y, (0, 255, 0));
+
+        buf
+    }
+
+    // Draw a wrapped 160x144 rectangle outline into a 256x256 RGBA buffer.
+    fn outline(&self, buf: &mut [u8], x: u8, y: u8, (r, g, b): (u8, u8, u8)) {
+        let mut plot = |px: u8, py: u8| {
+            let idx = (py as usize * 256 + px as usize) * SCREEN_DEPTH;
+            buf[idx] = r;
+            buf[idx + 1] = g;
+            buf[idx + 2] = b;
+            buf[idx + 3] = 255;
+        };
+        for dx in 0..SCREEN_WIDTH as u8 {
+            plot(x.wrapping_add(dx), y);
+            plot(x.wrapping_add(dx), y.wrapping_add(SCREEN_HEIGHT as u8 - 1));
+        }
+        for dy in 0..SCREEN_HEIGHT as u8 {
+            plot(x, y.wrapping_add(dy));
+            plot(x.wrapping_add(SCREEN_WIDTH as u8 - 1), y.wrapping_add(dy));
+        }
+    }
+
+    // Palette for the tile atlas, which has no map attributes of its own: the
+    // BG palette in DMG, CGB background palette 0 otherwise.
+    fn atlas_color(&self, color: u8) -> (u8, u8, u8) {
+        match self.emu_mode {
+            EmulationMode::Cgb => self.get_rgb_cgb(color, 0, false),
+            _ => self.get_rgb(color, self.dmgp.bgp),
+        }
+    }
+
     // ----------------------------------------------------------------------
     // Pixel FIFO
     // FIFO - 4MHz
@@ -224,7 +452,13 @@ impl Gpu {
     //  and Fetcher is restarted
     //
     // Sprites:
-    //  N/A
+    //  Mode 2 scans all 40 OAM entries and keeps up to 10 whose Y range covers
+    //  the current line. While the FIFO lays out pixels, once `lx + 8` reaches a
+    //  selected sprite's X the background fetcher is suspended, the sprite tile
+    //  row is fetched (honoring X/Y flip and the 8x16 tile-pair rule) and merged
+    //  into a parallel object FIFO. At pop time the sprite pixel wins unless it
+    //  is transparent (color 0) or its BG-over-OBJ bit is set over a non-zero
+    //  background pixel.
     //
     // Extra:
     //  LIJI says: "Only the uppermost 5 bits have an effect
@@ -237,6 +471,21 @@ impl Gpu {
             return;
         }
 
+        // Switch the fetcher over to the window the first time a window pixel is
+        // reached on this line: the BG FIFO is flushed and the fetcher restarted
+        // against the window tilemap. A WX < 7 window is shifted off the left
+        // edge, which we model by discarding its leading `7 - WX` pixels.
+        if !self.win_active && self.is_win_enabled() && self.is_win_pixel() {
+            self.win_active = true;
+            self.win_drawn = true;
+            self.bg_fifo.clear_fifo();
+            self.fetcher.x = 0;
+            self.fetcher.fetching = FetchType::Window;
+            self.fetcher.state = FetcherState::Sleep(0);
+            self.bg_fifo.scx = window_left_discard(self.position.window_x);
+            return;
+        }
+
         if self.bg_fifo.scx > 0 {
             self.bg_fifo.pop();
             self.bg_fifo.scx -= 1;
@@ -244,13 +493,155 @@ impl Gpu {
             return;
         }
 
-        let value = self.bg_fifo.pop();
-        let (r, g, b) = self.get_rgb(value, self.dmgp.bgp);
+        let bg_entry = self.bg_fifo.pop();
+        let obj = self.obj_fifo.pop_front();
+        let (r, g, b) = self.mix_pixel(bg_entry, obj);
         self.update_screen_row(self.position.lx as usize, r, g, b);
 
         self.position.lx += 1;
     }
 
+    // Resolve the final color for one screen pixel by merging the background
+    // pixel with the object FIFO front. The sprite wins unless it is transparent
+    // or either the sprite's own BG-over-OBJ bit or the CGB BG-to-OAM priority
+    // bit forces the (opaque) background in front.
+    fn mix_pixel(&self, bg_entry: u8, obj: Option<ObjPixel>) -> (u8, u8, u8) {
+        let bg_color = bg_pixel_color(bg_entry);
+        let bg_palette = bg_pixel_palette(bg_entry);
+        let bg_master_priority = bg_pixel_priority(bg_entry);
+
+        if let Some(o) = obj {
+            if obj_wins(o.color, o.bg_priority, bg_color, bg_master_priority) {
+                return self.obj_rgb(&o);
+            }
+        }
+
+        match self.emu_mode {
+            EmulationMode::Cgb => self.get_rgb_cgb(bg_color, bg_palette, false),
+            _ => self.get_rgb(bg_color, self.dmgp.bgp),
+        }
+    }
+
+    // Color of a winning object pixel through the appropriate palette path.
+    fn obj_rgb(&self, o: &ObjPixel) -> (u8, u8, u8) {
+        match self.emu_mode {
+            EmulationMode::Cgb => self.get_rgb_cgb(o.color, o.cgb_palette, true),
+            _ => self.get_rgb(o.color, if o.obp1 { self.dmgp.obp1 } else { self.dmgp.obp0 }),
+        }
+    }
+
+    // Mode 2 OAM scan: keep up to 10 sprites covering the current line, then
+    // order them for the merge step. DMG draws by ascending X (OAM index breaks
+    // ties); CGB draws purely by OAM index.
+    fn oam_search(&mut self) {
+        self.line_sprites.clear();
+        self.obj_fifo.clear();
+
+        // A new line always starts fetching the background; the window latches
+        // back on only once its pixel is reached again.
+        self.win_active = false;
+        self.fetcher.fetching = FetchType::Background;
+
+        let height = if self.lcdc.obj_size != 0 { 16 } else { 8 } as i16;
+        let ly = self.position.ly as i16;
+
+        for idx in 0..40 {
+            let base = idx * 4;
+            let y = self.oam[base];
+            if ly + 16 >= y as i16 && ly + 16 < y as i16 + height {
+                self.line_sprites.push(Sprite {
+                    oam_idx: idx as u8,
+                    y,
+                    x: self.oam[base + 1],
+                    tile_num: self.oam[base + 2],
+                    attr: self.oam[base + 3],
+                });
+                if self.line_sprites.len() == 10 {
+                    break;
+                }
+            }
+        }
+
+        match self.emu_mode {
+            EmulationMode::Cgb => self.line_sprites.sort_by_key(|s| s.oam_idx),
+            _ => self
+                .line_sprites
+                .sort_by(|a, b| a.x.cmp(&b.x).then(a.oam_idx.cmp(&b.oam_idx))),
+        }
+    }
+
+    // Fetch every selected sprite whose X has been reached by the FIFO, in
+    // priority order, suspending the background fetcher for each. Earlier (higher
+    // priority) sprites are merged first so lower-priority pixels only fill slots
+    // the winner left transparent.
+    fn obj_fetch_pending(&mut self) {
+        if self.lcdc.obj_display_enable == 0 || self.bg_fifo.size() < 8 {
+            return;
+        }
+
+        let trigger = self.position.lx as i16 + 8;
+        while let Some(pos) = self
+            .line_sprites
+            .iter()
+            .position(|s| s.x as i16 <= trigger)
+        {
+            let sprite = self.line_sprites.remove(pos);
+            self.fetch_sprite(&sprite);
+        }
+    }
+
+    // Fetch one sprite's tile row and merge its 8 pixels into the object FIFO.
+    // Honors X/Y flip, the 8x16 tile-pair rule (bit 0 of the index masked), and
+    // the CGB VRAM bank select in the attribute byte.
+    fn fetch_sprite(&mut self, sprite: &Sprite) {
+        let height = if self.lcdc.obj_size != 0 { 16 } else { 8 };
+        let y_flip = sprite.attr & 0x40 != 0;
+        let x_flip = sprite.attr & 0x20 != 0;
+        let obp1 = sprite.attr & 0x10 != 0;
+        let cgb_palette = (sprite.attr & 0x07) as usize;
+        let bg_priority = sprite.attr & 0x80 != 0;
+
+        let mut row = (self.position.ly as i16 + 16 - sprite.y as i16) as u16;
+        if y_flip {
+            row = height as u16 - 1 - row;
+        }
+
+        let tile_num = if height == 16 {
+            sprite.tile_num & 0xFE
+        } else {
+            sprite.tile_num
+        };
+        let bank = if self.emu_mode == EmulationMode::Cgb && sprite.attr & 0x08 != 0 {
+            1
+        } else {
+            0
+        };
+
+        let addr = 0x8000u16 + tile_num as u16 * 16 + row * 2;
+        let low = self.get_vram_byte(addr, bank);
+        let high = self.get_vram_byte(addr + 1, bank);
+
+        // The leftmost pixels of a sprite with X < 8 fall off the left edge.
+        let skip = (self.position.lx as i16 + 8 - sprite.x as i16).max(0) as usize;
+        for i in skip..8 {
+            let color = tile_color_at(low, high, i as u8, x_flip);
+
+            let slot = i - skip;
+            if slot >= self.obj_fifo.len() {
+                self.obj_fifo.push_back(ObjPixel::transparent());
+            }
+            // Keep the higher-priority pixel already present.
+            if color != 0 && self.obj_fifo[slot].color == 0 {
+                self.obj_fifo[slot] = ObjPixel {
+                    color,
+                    obp1,
+                    cgb_palette,
+                    bg_priority,
+                };
+            }
+        }
+    }
+
     fn fetcher_tick(&mut self) {
         match self.fetcher.state {
             FetcherState::Sleep(0) => {
@@ -263,9 +654,14 @@ impl Gpu {
                         let base = self.lcdc.bg_tilemap();
                         let row = self.position.ly.wrapping_add(self.position.scroll_y) / 8;
                         let col = (self.position.scroll_x / 8 + self.fetcher.x) % 32;
-                        self.fetcher.tile_num = self.get_byte(base + row as u16 * 32 + col as u16);
+                        self.read_map_entry(base + row as u16 * 32 + col as u16);
+                    }
+                    FetchType::Window => {
+                        let base = self.lcdc.win_tilemap();
+                        let row = (self.win_counter / 8) as u16;
+                        let col = self.fetcher.x as u16 % 32;
+                        self.read_map_entry(base + row * 32 + col);
                     }
-                    FetchType::Window => {}
                 }
                 self.fetcher.state = FetcherState::Sleep(1);
             }
@@ -274,10 +670,11 @@ impl Gpu {
             }
             // Fetch lower byte of current row from tile at tile number
             FetcherState::ReadTileDataLow => {
-                let row = self.position.ly.wrapping_add(self.position.scroll_y) % 8;
+                let row = self.fetch_tile_row();
                 let tile_addr =
                     self.tiledata_addr(self.lcdc.bg_tiledata_sel, self.fetcher.tile_num);
-                self.fetcher.low = self.get_byte(tile_addr + row as u16 * 2);
+                self.fetcher.low =
+                    self.get_vram_byte(tile_addr + row as u16 * 2, self.fetcher.bg_attr.bank);
                 self.fetcher.state = FetcherState::Sleep(2);
             }
             FetcherState::Sleep(2) => {
@@ -285,10 +682,11 @@ impl Gpu {
             }
             // Fetch upper byte of current row from tile at tile number
             FetcherState::ReadTileDataHigh => {
-                let row = self.position.ly.wrapping_add(self.position.scroll_y) % 8;
+                let row = self.fetch_tile_row();
                 let tile_addr =
                     self.tiledata_addr(self.lcdc.bg_tiledata_sel, self.fetcher.tile_num);
-                self.fetcher.high = self.get_byte(tile_addr + row as u16 * 2 + 1);
+                self.fetcher.high =
+                    self.get_vram_byte(tile_addr + row as u16 * 2 + 1, self.fetcher.bg_attr.bank);
                 self.fetcher.state = FetcherState::Push(0);
             }
             // Push tile row data to pixel FIFO
@@ -299,7 +697,13 @@ impl Gpu {
             // Push tile row data to pixel FIFO
             FetcherState::Push(1) => {
                 if self.bg_fifo.allow_push() {
-                    self.bg_fifo.push(self.fetcher.low, self.fetcher.high);
+                    self.bg_fifo.push(
+                        self.fetcher.low,
+                        self.fetcher.high,
+                        self.fetcher.bg_attr.palette_num,
+                        self.fetcher.bg_attr.priority,
+                        self.fetcher.bg_attr.xflip,
+                    );
                     self.fetcher.state = FetcherState::Sleep(0);
                 }
             }
@@ -307,6 +711,28 @@ impl Gpu {
         }
     }
 
+    // Row within the current tile: the window uses its own line counter, the
+    // background uses the scrolled LY. A vertically flipped CGB tile counts from
+    // the bottom.
+    fn fetch_tile_row(&self) -> u8 {
+        let row = match self.fetcher.fetching {
+            FetchType::Window => (self.win_counter % 8) as u8,
+            FetchType::Background => self.position.ly.wrapping_add(self.position.scroll_y) % 8,
+        };
+        flip_tile_row(row, self.fetcher.bg_attr.yflip)
+    }
+
+    // Read a tilemap entry: the tile number always comes from VRAM bank 0; in
+    // CGB the matching attribute byte is read from bank 1 and decoded, while DMG
+    // falls back to the neutral default.
+    fn read_map_entry(&mut self, map_addr: u16) {
+        self.fetcher.tile_num = self.get_vram_byte(map_addr, 0);
+        self.fetcher.bg_attr = match self.emu_mode {
+            EmulationMode::Cgb => BgAttr::from(self.get_vram_byte(map_addr, 1)),
+            _ => BgAttr::default(),
+        };
+    }
+
     fn tiledata_addr(&self, sel: u8, idx: u8) -> u16 {
         if sel == 0 {
             0x8800u16 + (idx as i8 as i16 + 128) as u16 * 16
@@ -335,13 +761,12 @@ impl Gpu {
         self.lcd[ly * SCREEN_WIDTH * SCREEN_DEPTH + x * SCREEN_DEPTH + 3] = 255;
     }
 
+    // Map a 2-bit DMG color through the monochrome palette register and then
+    // the selectable shade table. Background, window and sprite layers all share
+    // this path so the whole DMG frame stays visually consistent.
     fn get_rgb(&self, value: u8, palette: u8) -> (u8, u8, u8) {
-        match (palette >> (2 * value)) & 0x03 {
-            0 => (224, 247, 208),
-            1 => (136, 192, 112),
-            2 => (52, 104, 86),
-            _ => (8, 23, 33),
-        }
+        let shade = (palette >> (2 * value)) & 0x03;
+        self.dmg_palette[shade as usize]
     }
 
     fn get_rgb_cgb(&self, color_num: u8, palette_num: usize, obp: bool) -> (u8, u8, u8) {
@@ -370,6 +795,11 @@ impl Gpu {
     }
 
     pub fn tick(&mut self, mut cycles: usize) {
+        // OAM DMA runs independently of the LCD, so pump it before the display
+        // gate and mirror its state onto the bus-visible flag.
+        self.oam_dma.tick(cycles, &mut self.oam);
+        self.oam_dma_active = self.oam_dma.active;
+
         if self.lcdc.display_enable == 0 {
             return;
         }
@@ -388,6 +818,7 @@ impl Gpu {
         if self.clock + cycles >= 80 {
             let cycles_left = self.clock + cycles - 80;
             self.clock = 0;
+            self.oam_search();
             self.change_mode(GpuMode::PixelTransfer);
             cycles_left
         } else {
@@ -399,6 +830,7 @@ impl Gpu {
     fn pixel_transfer_tick(&mut self, mut cycles: usize) -> usize {
         while cycles > 0 && (self.position.lx as usize) < SCREEN_WIDTH {
             self.fetcher_tick();
+            self.obj_fetch_pending();
             self.bg_fifo_tick();
             cycles -= 1
         }
@@ -414,10 +846,19 @@ impl Gpu {
         if self.clock + cycles >= 204 - self.borrowed_cycles {
             let cycles_left = self.clock + cycles - (204 - self.borrowed_cycles);
             self.clock = 0;
+
+            // The window line counter only advances on lines that actually drew
+            // a window pixel, independent of LY.
+            if self.win_drawn {
+                self.win_counter += 1;
+                self.win_drawn = false;
+            }
+
             self.position.ly += 1;
             self.check_coincidence();
 
             if self.position.ly > 143 {
+                self.win_counter = 0;
                 self.change_mode(GpuMode::VBlank);
                 self.request_vblank_interrupt();
             } else {
@@ -589,6 +1030,7 @@ impl Gpu {
             0xFF43 => self.position.scroll_x = value,
             0xFF44 => (),
             0xFF45 => self.position.lyc = value,
+            0xFF46 => self.oam_dma.start(value),
             0xFF47 => self.dmgp.bgp = value,
             0xFF48 => self.dmgp.obp0 = value,
             0xFF49 => self.dmgp.obp1 = value,
@@ -689,4 +1131,130 @@ impl Gpu {
     fn request_vblank_interrupt(&mut self) {
         self.request_vblank_int = true;
     }
+
+    // OAM DMA source feed. The GPU owns only VRAM, so it cannot read the DMA
+    // source region (ROM/WRAM) itself — the bus must drive the feed. DEPENDENCY:
+    // the memory bus / CPU step loop is required to, after every `set_byte`,
+    // poll `oam_dma_request()` and, when it returns `Some(base)`, read the 160
+    // bytes `base ..= base | 0x9F` and hand them back via `oam_dma_fill()`. Until
+    // that wiring lands (it lives in the bus module, outside this chunk), a
+    // started transfer stays pending and never copies into OAM. Once fed, the
+    // PPU paces the copy into OAM from `tick`.
+    pub fn oam_dma_request(&self) -> Option<u16> {
+        self.oam_dma.request()
+    }
+
+    pub fn oam_dma_fill(&mut self, bytes: &[u8]) {
+        self.oam_dma.fill(bytes);
+    }
+
+    // Select one of the shipped DMG color schemes at runtime.
+    pub fn set_dmg_palette(&mut self, palette: DmgPalette) {
+        self.dmg_palette = palette.shades();
+    }
+
+    // Install a fully custom four-shade DMG table.
+    pub fn set_dmg_shades(&mut self, shades: [(u8, u8, u8); 4]) {
+        self.dmg_palette = shades;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bg_pixel_color, bg_pixel_palette, bg_pixel_priority, flip_tile_row, obj_wins,
+        pack_bg_pixel, tile_color_at, window_left_discard,
+    };
+
+    #[test]
+    fn window_left_edge_discard() {
+        // A window at WX >= 7 sits on-screen and discards nothing.
+        assert_eq!(window_left_discard(7), 0);
+        assert_eq!(window_left_discard(80), 0);
+        // WX < 7 shifts the window off the left edge by `7 - WX` pixels.
+        assert_eq!(window_left_discard(6), 1);
+        assert_eq!(window_left_discard(0), 7);
+        // The discard never exceeds a tile's worth of pixels.
+        for wx in 0..=166u8 {
+            assert!(window_left_discard(wx) < 8);
+        }
+    }
+
+    #[test]
+    fn tile_color_reads_bits_msb_first() {
+        // low = 0b1100_0000, high = 0b1000_0000: the high byte is the MSB of the
+        // 2-bit color, so pixel 0 = 0b11, pixel 1 = 0b01, pixel 2 = 0b00.
+        let (low, high) = (0b1100_0000, 0b1000_0000);
+        assert_eq!(tile_color_at(low, high, 0, false), 0b11);
+        assert_eq!(tile_color_at(low, high, 1, false), 0b01);
+        assert_eq!(tile_color_at(low, high, 2, false), 0b00);
+    }
+
+    #[test]
+    fn tile_color_xflip_mirrors_the_row() {
+        let (low, high) = (0b1100_0000, 0b1000_0000);
+        // Flipping swaps pixel i with pixel 7 - i.
+        for x in 0..8u8 {
+            assert_eq!(
+                tile_color_at(low, high, x, true),
+                tile_color_at(low, high, 7 - x, false)
+            );
+        }
+    }
+
+    #[test]
+    fn tile_row_vertical_flip() {
+        assert_eq!(flip_tile_row(0, false), 0);
+        assert_eq!(flip_tile_row(0, true), 7);
+        assert_eq!(flip_tile_row(3, true), 4);
+        assert_eq!(flip_tile_row(7, true), 0);
+    }
+
+    #[test]
+    fn bg_pixel_pack_round_trips() {
+        for color in 0..4u8 {
+            for palette in 0..8u8 {
+                for priority in [false, true] {
+                    let entry = pack_bg_pixel(color, palette, priority);
+                    assert_eq!(bg_pixel_color(entry), color);
+                    assert_eq!(bg_pixel_palette(entry), palette as usize);
+                    assert_eq!(bg_pixel_priority(entry), priority);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bg_pixel_fields_do_not_overlap() {
+        // A fully-set entry must not let one field bleed into another.
+        let entry = pack_bg_pixel(0b11, 0b111, true);
+        assert_eq!(bg_pixel_color(entry), 0b11);
+        assert_eq!(bg_pixel_palette(entry), 0b111);
+        assert!(bg_pixel_priority(entry));
+    }
+
+    #[test]
+    fn transparent_sprite_never_wins() {
+        // Color 0 is transparent regardless of any priority bit.
+        assert!(!obj_wins(0, false, 0, false));
+        assert!(!obj_wins(0, false, 3, false));
+    }
+
+    #[test]
+    fn opaque_sprite_over_blank_background() {
+        // An opaque sprite always beats a color-0 background, even when a
+        // priority bit is set.
+        assert!(obj_wins(2, true, 0, false));
+        assert!(obj_wins(2, false, 0, true));
+    }
+
+    #[test]
+    fn background_priority_keeps_sprite_behind() {
+        // Over an opaque background, either the sprite's BG-over-OBJ bit or the
+        // CGB master priority bit keeps the sprite behind.
+        assert!(!obj_wins(2, true, 1, false));
+        assert!(!obj_wins(2, false, 1, true));
+        // With neither bit set the opaque sprite still wins.
+        assert!(obj_wins(2, false, 1, false));
+    }
 }